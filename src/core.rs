@@ -2,6 +2,7 @@ use std::borrow::Cow;
 use std::collections::HashMap;
 use std::fmt::Debug;
 use std::path::PathBuf;
+use std::time::{Duration, Instant};
 
 use rocket::config::{self, Config, ConfigError, Table, Value};
 use rocket::fairing::{AdHoc, Fairing};
@@ -15,6 +16,70 @@ use rocket::{Data, FromForm, Route, State};
 use serde_json::Value as JsonValue;
 
 const STATE_COOKIE_NAME: &str = "rocket_oauth2_state";
+const PKCE_COOKIE_NAME: &str = "rocket_oauth2_pkce";
+const IDP_COOKIE_NAME: &str = "rocket_oauth2_idp";
+const NONCE_COOKIE_NAME: &str = "rocket_oauth2_oidc_nonce";
+
+/// The unreserved character set from RFC 7636 §4.1, used to build high-entropy
+/// tokens such as PKCE code verifiers and OIDC nonces.
+const TOKEN_CHARSET: &[u8] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-._~";
+
+/// The length, in characters, of generated code verifiers. RFC 7636 §4.1
+/// allows anywhere from 43 to 128.
+const PKCE_VERIFIER_LENGTH: usize = 96;
+
+/// The length, in characters, of generated OIDC nonces.
+const OIDC_NONCE_LENGTH: usize = 32;
+
+/// The clock skew, in seconds, allowed when validating an `id_token`'s
+/// `exp`/`iat` claims.
+const OIDC_CLOCK_SKEW_SECS: u64 = 60;
+
+/// Generate a high-entropy, URL-safe random string of the given length, drawn
+/// from the RFC 7636 §4.1 unreserved character set.
+fn random_token(length: usize) -> String {
+    use rand::Rng;
+    let mut rng = rand::thread_rng();
+    (0..length)
+        .map(|_| TOKEN_CHARSET[rng.gen_range(0..TOKEN_CHARSET.len())] as char)
+        .collect()
+}
+
+/// Generate a high-entropy `code_verifier` as described in RFC 7636 §4.1.
+fn generate_pkce_verifier() -> String {
+    random_token(PKCE_VERIFIER_LENGTH)
+}
+
+/// Derive the `code_challenge` for the `S256` method from a `code_verifier`,
+/// as described in RFC 7636 §4.2.
+fn pkce_code_challenge(verifier: &str) -> String {
+    use sha2::{Digest, Sha256};
+    let digest = Sha256::digest(verifier.as_bytes());
+    base64::encode_config(&digest, base64::URL_SAFE_NO_PAD)
+}
+
+/// Generate a `nonce` value to bind an authorization request to its
+/// `id_token`, as described in OpenID Connect Core §3.1.2.1.
+fn generate_oidc_nonce() -> String {
+    random_token(OIDC_NONCE_LENGTH)
+}
+
+/// Remove the PKCE verifier and OIDC nonce cookies, if either was planted for
+/// this flow. `handle` calls this when the `state` check fails, when the
+/// provider redirects back with an error, and when the token exchange fails,
+/// so both single-use values are dropped even on paths that never read them
+/// back. Requests rejected before the `state` check (no query string, a
+/// missing or unknown IdP cookie, a query with neither `code` nor `error`)
+/// leave them in place.
+fn clear_pkce_and_nonce_cookies(cookies: &mut Cookies<'_>) {
+    if let Some(pkce_cookie) = cookies.get_private(PKCE_COOKIE_NAME) {
+        cookies.remove(pkce_cookie.clone());
+    }
+    if let Some(nonce_cookie) = cookies.get_private(NONCE_COOKIE_NAME) {
+        cookies.remove(nonce_cookie.clone());
+    }
+}
 
 /// The server's response to a successful token exchange, defined in
 /// in RFC 6749 §5.1.
@@ -37,6 +102,230 @@ pub struct TokenResponse {
     /// Additional values returned by the authorization server, if any.
     #[serde(flatten)]
     pub extras: HashMap<String, JsonValue>,
+
+    /// The instant at which the access token expires, computed from
+    /// `expires_in` when the token is received. Not part of the wire format.
+    #[serde(skip)]
+    expires_at: Option<Instant>,
+
+    /// The validated claims from `extras["id_token"]`, if OpenID Connect is
+    /// enabled for the provider and the token response included one. Not
+    /// part of the wire format; populated after validation.
+    #[serde(skip)]
+    id_claims: Option<IdClaims>,
+}
+
+impl TokenResponse {
+    /// Stamps `expires_at` from `expires_in`, relative to now. Should be
+    /// called immediately after a token is received from the server.
+    fn stamp_expiry(&mut self) {
+        self.expires_at = self
+            .expires_in
+            .map(|secs| Instant::now() + Duration::from_secs(secs.max(0) as u64));
+    }
+
+    /// Returns the instant at which the access token expires, if the
+    /// authorization server provided an `expires_in` value.
+    pub fn expires_at(&self) -> Option<Instant> {
+        self.expires_at
+    }
+
+    /// Returns `true` if the access token has an expiry and that expiry has
+    /// passed.
+    pub fn is_expired(&self) -> bool {
+        match self.expires_at {
+            Some(expires_at) => Instant::now() >= expires_at,
+            None => false,
+        }
+    }
+
+    /// Returns the validated claims from this token's `id_token`, if OpenID
+    /// Connect is enabled for the provider and the token response included
+    /// one.
+    pub fn id_claims(&self) -> Option<&IdClaims> {
+        self.id_claims.as_ref()
+    }
+}
+
+/// The claims carried by a validated OpenID Connect `id_token`, as described
+/// in OpenID Connect Core §2 and §5.1.
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct IdClaims {
+    /// The subject identifier: a stable, unique identifier for the user
+    /// within the issuer.
+    pub sub: String,
+    /// The user's email address, present if the `email` scope was granted.
+    pub email: Option<String>,
+    /// The user's full name, present if the `profile` scope was granted.
+    pub name: Option<String>,
+
+    /// The nonce this token was issued for. Checked against the nonce planted
+    /// in the authorization request during validation; not exposed to
+    /// callers.
+    #[serde(default)]
+    nonce: Option<String>,
+
+    /// Additional claims returned by the provider, if any.
+    #[serde(flatten)]
+    pub extras: HashMap<String, JsonValue>,
+}
+
+/// The ways validating an `id_token` can fail.
+#[derive(Debug)]
+enum OidcError {
+    /// The provider has no `issuer`/`jwks_uri` configured.
+    NotConfigured,
+    /// The provider's JWKS document could not be fetched or parsed.
+    Jwks(reqwest::Error),
+    /// The `id_token`'s signature or standard claims failed validation.
+    Token(jsonwebtoken::errors::Error),
+    /// A claim required by the OIDC flow didn't have the expected value.
+    Claim(&'static str),
+}
+
+/// Determine the signature algorithm a JWK is allowed to verify. The `alg` on
+/// the token's own header is attacker-controlled and must never be trusted
+/// for this; instead prefer the JWK's own `alg` member and fall back to the
+/// algorithm implied by its key type, per RFC 7518 §6.
+fn jwk_algorithm(jwk: &jsonwebtoken::jwk::Jwk) -> Result<jsonwebtoken::Algorithm, OidcError> {
+    use jsonwebtoken::jwk::{AlgorithmParameters, EllipticCurveKeyType, KeyAlgorithm};
+    use jsonwebtoken::Algorithm;
+
+    if let Some(alg) = jwk.common.key_algorithm {
+        return match alg {
+            KeyAlgorithm::RS256 => Ok(Algorithm::RS256),
+            KeyAlgorithm::RS384 => Ok(Algorithm::RS384),
+            KeyAlgorithm::RS512 => Ok(Algorithm::RS512),
+            KeyAlgorithm::PS256 => Ok(Algorithm::PS256),
+            KeyAlgorithm::PS384 => Ok(Algorithm::PS384),
+            KeyAlgorithm::PS512 => Ok(Algorithm::PS512),
+            KeyAlgorithm::ES256 => Ok(Algorithm::ES256),
+            KeyAlgorithm::ES384 => Ok(Algorithm::ES384),
+            KeyAlgorithm::EdDSA => Ok(Algorithm::EdDSA),
+            _ => Err(OidcError::Claim("alg")),
+        };
+    }
+
+    // The JWK didn't advertise `alg` directly; every major IdP's JWKS still
+    // publishes a key type we can map to the one algorithm it's meant for.
+    match &jwk.algorithm {
+        AlgorithmParameters::RSA(_) => Ok(Algorithm::RS256),
+        AlgorithmParameters::EllipticCurve(params) => match params.curve {
+            EllipticCurveKeyType::P256 => Ok(Algorithm::ES256),
+            EllipticCurveKeyType::P384 => Ok(Algorithm::ES384),
+            _ => Err(OidcError::Claim("alg")),
+        },
+        _ => Err(OidcError::Claim("alg")),
+    }
+}
+
+/// Validate an `id_token` against the provider's JWKS and standard claims,
+/// per OpenID Connect Core §3.1.3.7, and return its claims.
+fn validate_id_token(id_token: &str, config: &OAuthConfig, nonce: &str) -> Result<IdClaims, OidcError> {
+    use jsonwebtoken::jwk::JwkSet;
+    use jsonwebtoken::{decode, decode_header, DecodingKey, Validation};
+
+    let provider = config.provider();
+    let issuer = provider.issuer.as_deref().ok_or(OidcError::NotConfigured)?;
+    let jwks_uri = provider.jwks_uri.as_deref().ok_or(OidcError::NotConfigured)?;
+
+    let jwks: JwkSet = reqwest::blocking::get(jwks_uri)
+        .and_then(|response| response.json())
+        .map_err(OidcError::Jwks)?;
+
+    let header = decode_header(id_token).map_err(OidcError::Token)?;
+    let kid = header.kid.as_deref().ok_or(OidcError::Claim("kid"))?;
+    let jwk = jwks.find(kid).ok_or(OidcError::Claim("kid"))?;
+    let decoding_key = DecodingKey::from_jwk(jwk).map_err(OidcError::Token)?;
+
+    // Pin the accepted algorithm to what the key itself advertises rather
+    // than the token's own (attacker-controlled) header, so a forged header
+    // can't smuggle a weaker or mismatched algorithm past verification.
+    let algorithm = jwk_algorithm(jwk)?;
+    let mut validation = Validation::new(algorithm);
+    validation.set_audience(&[config.client_id()]);
+    validation.set_issuer(&[issuer]);
+    validation.leeway = OIDC_CLOCK_SKEW_SECS;
+
+    let data = decode::<IdClaims>(id_token, &decoding_key, &validation).map_err(OidcError::Token)?;
+
+    // `Validation` checks `exp` with the leeway above but not `iat`; reject a
+    // token without one, or issued further in the future than the skew allows.
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    match data.claims.extras.get("iat").and_then(JsonValue::as_f64) {
+        Some(iat) if iat <= (now + OIDC_CLOCK_SKEW_SECS) as f64 => {}
+        _ => return Err(OidcError::Claim("iat")),
+    }
+
+    if data.claims.nonce.as_deref() != Some(nonce) {
+        return Err(OidcError::Claim("nonce"));
+    }
+
+    Ok(data.claims)
+}
+
+/// An error returned by the authorization server, as described in
+/// RFC 6749 §4.1.2.1 (authorization errors) and §5.2 (token errors).
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct OAuthError {
+    /// The error code returned by the authorization server.
+    pub error: OAuthErrorCode,
+    /// A human-readable description of the error, if the server provided one.
+    pub error_description: Option<String>,
+    /// A URI identifying a human-readable web page with information about
+    /// the error, if the server provided one.
+    pub error_uri: Option<String>,
+}
+
+/// The error codes defined in RFC 6749 §5.2, reused for the authorization
+/// error redirect described in §4.1.2.1. `Other` preserves any error code
+/// the RFC doesn't define, verbatim.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OAuthErrorCode {
+    /// The request is missing a required parameter, includes an unsupported
+    /// parameter value, or is otherwise malformed.
+    InvalidRequest,
+    /// Client authentication failed.
+    InvalidClient,
+    /// The provided authorization grant or refresh token is invalid, expired,
+    /// revoked, or was issued to another client.
+    InvalidGrant,
+    /// The authenticated client is not authorized to use this grant type.
+    UnauthorizedClient,
+    /// The authorization grant type is not supported by the authorization
+    /// server.
+    UnsupportedGrantType,
+    /// The requested scope is invalid, unknown, malformed, or exceeds the
+    /// scope granted by the resource owner.
+    InvalidScope,
+    /// An error code not defined by RFC 6749.
+    Other(String),
+}
+
+impl From<String> for OAuthErrorCode {
+    fn from(error: String) -> OAuthErrorCode {
+        match error.as_str() {
+            "invalid_request" => OAuthErrorCode::InvalidRequest,
+            "invalid_client" => OAuthErrorCode::InvalidClient,
+            "invalid_grant" => OAuthErrorCode::InvalidGrant,
+            "unauthorized_client" => OAuthErrorCode::UnauthorizedClient,
+            "unsupported_grant_type" => OAuthErrorCode::UnsupportedGrantType,
+            "invalid_scope" => OAuthErrorCode::InvalidScope,
+            _ => OAuthErrorCode::Other(error),
+        }
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for OAuthErrorCode {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        String::deserialize(deserializer).map(OAuthErrorCode::from)
+    }
 }
 
 /// An OAuth2 `Adapater` can be implemented by any type that facilitates the
@@ -49,16 +338,61 @@ pub trait Adapter: Send + Sync + 'static {
     type Error: Debug;
 
     /// Generate an authorization URI and state value as described by RFC 6749 §4.1.1.
+    ///
+    /// If `pkce_code_challenge` is `Some`, the implementor must append it to
+    /// the authorization URI as `code_challenge=<challenge>&code_challenge_method=S256`,
+    /// per RFC 7636 §4.3.
+    ///
+    /// If `oidc_nonce` is `Some`, the implementor must append it to the
+    /// authorization URI as `nonce=<value>`, per OpenID Connect Core §3.1.2.1.
     fn authorization_uri(
         &self,
         config: &OAuthConfig,
         scopes: &[&str],
+        pkce_code_challenge: Option<&str>,
+        oidc_nonce: Option<&str>,
     ) -> Result<(Absolute<'static>, String), Self::Error>;
 
     /// Perform the token exchange in accordance with RFC 6749 §4.1.3 given the
     /// authorization code provided by the service.
-    fn exchange_code(&self, config: &OAuthConfig, code: &str)
-        -> Result<TokenResponse, Self::Error>;
+    ///
+    /// If `pkce_code_verifier` is `Some`, the implementor must include it in
+    /// the token request as `code_verifier=<verifier>`, per RFC 7636 §4.5.
+    fn exchange_code(
+        &self,
+        config: &OAuthConfig,
+        code: &str,
+        pkce_code_verifier: Option<&str>,
+    ) -> Result<TokenResponse, Self::Error>;
+
+    /// Redeem a refresh token for a new access token, in accordance with
+    /// RFC 6749 §6.
+    fn refresh(
+        &self,
+        config: &OAuthConfig,
+        refresh_token: &str,
+    ) -> Result<TokenResponse, Self::Error>;
+
+    /// Perform the Client Credentials grant in accordance with RFC 6749 §4.4,
+    /// returning an app-level access token with no resource owner involved.
+    fn client_credentials(
+        &self,
+        config: &OAuthConfig,
+        scopes: &[&str],
+    ) -> Result<TokenResponse, Self::Error>;
+
+    /// Decode the error returned by a failed `exchange_code` into the same
+    /// `OAuthError` shape produced by the authorization-redirect `error=`
+    /// case, so `OAuth2::handle` can route a token-endpoint failure (e.g. an
+    /// `invalid_grant`/`invalid_scope` body per RFC 6749 §5.2) to
+    /// `Callback::error` identically. Implementors whose `Error` wraps the
+    /// token endpoint's JSON error body should override this; the default
+    /// implementation returns `None`, in which case the failure surfaces as
+    /// an opaque `400 Bad Request` instead.
+    fn token_error(&self, error: &Self::Error) -> Option<OAuthError> {
+        let _ = error;
+        None
+    }
 }
 
 /// An OAuth2 `Callback` implements application-specific OAuth client logic,
@@ -71,21 +405,39 @@ pub trait Callback: Send + Sync + 'static {
     type Responder: Responder<'static>;
 
     /// This method will be called when a token exchange has successfully
-    /// completed and will be provided with the request and the token.
-    /// Implementors should perform application-specific logic here, such as
-    /// checking a database or setting a login cookie.
-    fn callback(&self, request: &Request<'_>, token: TokenResponse) -> Self::Responder;
+    /// completed and will be provided with the request, the id of the IdP
+    /// that authenticated the user, and the token. Implementors should
+    /// perform application-specific logic here, such as checking a database
+    /// or setting a login cookie.
+    fn callback(&self, request: &Request<'_>, idp: &str, token: TokenResponse) -> Self::Responder;
+
+    /// This method will be called when the authorization server redirects
+    /// back with an error instead of an authorization code, e.g. because the
+    /// resource owner denied the request (RFC 6749 §4.1.2.1), and when a
+    /// failed token exchange is decoded by `Adapter::token_error`.
+    /// Implementors can override this to render a friendly "access denied"
+    /// page or special-case specific error codes. The default implementation
+    /// fails the request with `400 Bad Request`.
+    fn error(
+        &self,
+        request: &Request<'_>,
+        idp: &str,
+        error: OAuthError,
+    ) -> Result<Self::Responder, Status> {
+        let _ = (request, idp, error);
+        Err(Status::BadRequest)
+    }
 }
 
 impl<F, R> Callback for F
 where
-    F: Fn(&Request<'_>, TokenResponse) -> R + Send + Sync + 'static,
+    F: Fn(&Request<'_>, &str, TokenResponse) -> R + Send + Sync + 'static,
     R: Responder<'static>,
 {
     type Responder = R;
 
-    fn callback(&self, request: &Request<'_>, token: TokenResponse) -> Self::Responder {
-        (self)(request, token)
+    fn callback(&self, request: &Request<'_>, idp: &str, token: TokenResponse) -> Self::Responder {
+        (self)(request, idp, token)
     }
 }
 
@@ -96,6 +448,8 @@ pub struct OAuthConfig {
     client_id: String,
     client_secret: String,
     redirect_uri: String,
+    pkce: bool,
+    oidc: bool,
 }
 
 fn get_config_string(table: &Table, key: &str) -> config::Result<String> {
@@ -110,6 +464,18 @@ fn get_config_string(table: &Table, key: &str) -> config::Result<String> {
     Ok(string.to_string())
 }
 
+fn get_config_string_opt(table: &Table, key: &str) -> config::Result<Option<String>> {
+    match table.get(key) {
+        Some(value) => {
+            let string = value.as_str().ok_or_else(|| {
+                ConfigError::BadType(key.into(), "string", value.type_str(), "".into())
+            })?;
+            Ok(Some(string.to_string()))
+        }
+        None => Ok(None),
+    }
+}
+
 impl OAuthConfig {
     /// Create a new OAuthConfig.
     pub fn new(
@@ -117,12 +483,16 @@ impl OAuthConfig {
         client_id: String,
         client_secret: String,
         redirect_uri: String,
+        pkce: bool,
+        oidc: bool,
     ) -> OAuthConfig {
         OAuthConfig {
             provider,
             client_id,
             client_secret,
             redirect_uri,
+            pkce,
+            oidc,
         }
     }
 
@@ -145,15 +515,41 @@ impl OAuthConfig {
         let client_id = get_config_string(table, "client_id")?;
         let client_secret = get_config_string(table, "client_secret")?;
         let redirect_uri = get_config_string(table, "redirect_uri")?;
+        let pkce = match table.get("pkce") {
+            Some(v) => v
+                .as_bool()
+                .ok_or_else(|| ConfigError::BadType("pkce".into(), "bool", v.type_str(), "".into()))?,
+            None => false,
+        };
+        let oidc = match table.get("oidc") {
+            Some(v) => v
+                .as_bool()
+                .ok_or_else(|| ConfigError::BadType("oidc".into(), "bool", v.type_str(), "".into()))?,
+            None => false,
+        };
 
         Ok(OAuthConfig::new(
             provider,
             client_id,
             client_secret,
             redirect_uri,
+            pkce,
+            oidc,
         ))
     }
 
+    /// Constructs every `OAuthConfig` configured under the `oauth` table,
+    /// keyed by its table name (e.g. `[oauth.github]` and `[oauth.google]`
+    /// produce `"github"` and `"google"` entries). This is the entry point
+    /// for registry-mode multi-provider setups; see [OAuth2::fairing].
+    pub fn from_config_all(config: &Config) -> config::Result<HashMap<String, OAuthConfig>> {
+        let oauth = config.get_table("oauth")?;
+        oauth
+            .keys()
+            .map(|name| OAuthConfig::from_config(config, name).map(|c| (name.clone(), c)))
+            .collect()
+    }
+
     /// Gets the [Provider] for this configuration.
     pub fn provider(&self) -> &Provider {
         &self.provider
@@ -173,11 +569,35 @@ impl OAuthConfig {
     pub fn redirect_uri(&self) -> &str {
         &self.redirect_uri
     }
+
+    /// Returns whether PKCE (RFC 7636) is enabled for this configuration.
+    pub fn pkce(&self) -> bool {
+        self.pkce
+    }
+
+    /// Returns whether OpenID Connect `id_token` validation is enabled for
+    /// this configuration. This is opt-in (an `oidc = true` key in the oauth
+    /// table, mirroring `pkce`) and only takes effect if the provider also
+    /// has an `issuer` and a `jwks_uri` configured; a provider that merely
+    /// advertises OIDC metadata does not enable validation on its own.
+    pub fn oidc(&self) -> bool {
+        self.oidc && self.provider.issuer.is_some() && self.provider.jwks_uri.is_some()
+    }
 }
 
 /// The `OAuth2` structure implements OAuth in a Rocket application by setting
 /// up OAuth-related route handlers.
 ///
+/// ## Multiple providers
+/// `OAuth2` can be configured with several named providers (an IdP id such as
+/// `github` or `google` mapped to an `OAuthConfig`), so that one mounted
+/// instance can offer "Log in with GitHub" and "Log in with Google" side by
+/// side. The login route should include a `<idp>` dynamic path segment (e.g.
+/// `/login/<idp>`); if it doesn't, the IdP id is instead read from an `idp`
+/// query parameter. Whichever IdP was used to start the flow is recorded in a
+/// cookie so the redirect handler can dispatch the token exchange to the
+/// matching config, and is passed to the `Callback` as well.
+///
 /// ## Redirect handler
 /// `OAuth2` handles the redirect URI. It verifies the `state` token to prevent
 /// CSRF attacks, then instructs the Adapter to perform the token exchange. The
@@ -191,37 +611,39 @@ impl OAuthConfig {
 pub struct OAuth2<A, C> {
     adapter: A,
     callback: C,
-    config: OAuthConfig,
-    default_scopes: Vec<String>,
+    configs: HashMap<String, OAuthConfig>,
+    default_scopes: HashMap<String, Vec<String>>,
 }
 
 impl<A: Adapter, C: Callback> OAuth2<A, C> {
     /// Returns an OAuth2 fairing. The fairing will place an instance of
     /// `OAuth2<A, C>` in managed state and mount a redirect handler. It will
-    /// also mount a login handler if `login` is `Some`.
-    pub fn fairing<CN, CU, LU, LS>(
+    /// also mount a login handler if `login` is `Some`. Every provider
+    /// configured under the `oauth` table (see [OAuthConfig::from_config_all])
+    /// is made available, keyed by its table name.
+    pub fn fairing<CU, LU, LS>(
         adapter: A,
         callback: C,
-        config_name: CN,
         callback_uri: CU,
-        login: Option<(LU, Vec<LS>)>,
+        login: Option<(LU, HashMap<String, Vec<LS>>)>,
     ) -> impl Fairing
     where
-        CN: Into<Cow<'static, str>>,
         CU: Into<Cow<'static, str>>,
         LU: Into<Cow<'static, str>>,
         LS: Into<String>,
     {
-        let config_name = config_name.into();
         let callback_uri = callback_uri.into();
-        let mut login = login.map(|login| {
+        let mut login = login.map(|(login_uri, login_scopes)| {
             (
-                login.0.into(),
-                login.1.into_iter().map(Into::into).collect(),
+                login_uri.into(),
+                login_scopes
+                    .into_iter()
+                    .map(|(idp, scopes)| (idp, scopes.into_iter().map(Into::into).collect()))
+                    .collect::<HashMap<String, Vec<String>>>(),
             )
         });
         AdHoc::on_attach("OAuth Init", move |rocket| {
-            let config = match OAuthConfig::from_config(rocket.config(), &config_name) {
+            let configs = match OAuthConfig::from_config_all(rocket.config()) {
                 Ok(c) => c,
                 Err(e) => {
                     log::error!("Invalid configuration: {:?}", e);
@@ -231,14 +653,14 @@ impl<A: Adapter, C: Callback> OAuth2<A, C> {
 
             let login = login
                 .as_mut()
-                .map(|l: &mut (Cow<'static, str>, Vec<String>)| {
-                    (l.0.as_ref(), l.1.drain(..).collect())
+                .map(|l: &mut (Cow<'static, str>, HashMap<String, Vec<String>>)| {
+                    (l.0.as_ref(), std::mem::take(&mut l.1))
                 });
 
             Ok(rocket.attach(Self::custom(
                 adapter,
                 callback,
-                config,
+                configs,
                 &callback_uri,
                 login,
             )))
@@ -248,13 +670,14 @@ impl<A: Adapter, C: Callback> OAuth2<A, C> {
     /// Returns an OAuth2 fairing with custom configuration. The fairing will
     /// place an instance of `OAuth2<A, C>` in managed state and mount a
     /// redirect handler. It will also mount a login handler if `login` is
-    /// `Some`.
+    /// `Some`. `configs` maps each IdP id (e.g. `github`, `google`) to the
+    /// `OAuthConfig` used when a flow selects it.
     pub fn custom(
         adapter: A,
         callback: C,
-        config: OAuthConfig,
+        configs: HashMap<String, OAuthConfig>,
         callback_uri: &str,
-        login: Option<(&str, Vec<String>)>,
+        login: Option<(&str, HashMap<String, Vec<String>>)>,
     ) -> impl Fairing {
         let mut routes = Vec::new();
 
@@ -264,7 +687,7 @@ impl<A: Adapter, C: Callback> OAuth2<A, C> {
             redirect_handler::<A, C>,
         ));
 
-        let mut default_scopes = vec![];
+        let mut default_scopes = HashMap::new();
         if let Some((login_uri, login_scopes)) = login {
             routes.push(Route::new(Method::Get, login_uri, login_handler::<A, C>));
             default_scopes = login_scopes;
@@ -273,7 +696,7 @@ impl<A: Adapter, C: Callback> OAuth2<A, C> {
         let oauth2 = Self {
             adapter,
             callback,
-            config,
+            configs,
             default_scopes,
         };
 
@@ -282,36 +705,179 @@ impl<A: Adapter, C: Callback> OAuth2<A, C> {
         })
     }
 
-    /// Prepare an authentication redirect. This sets a state cookie and returns
-    /// a `Redirect` to the provider's authorization page.
+    /// Prepare an authentication redirect for the given IdP. This sets a
+    /// state cookie, a cookie recording the chosen IdP, (if PKCE is enabled
+    /// for that IdP) a code verifier cookie, and (if OpenID Connect is
+    /// enabled for that IdP) a nonce cookie, then returns a
+    /// `Redirect` to the provider's authorization page. Returns `None` if
+    /// `idp` does not match any configured provider.
     pub fn get_redirect(
         &self,
         cookies: &mut Cookies<'_>,
+        idp: &str,
         scopes: &[&str],
-    ) -> Result<Redirect, A::Error> {
-        let (uri, state) = self.adapter.authorization_uri(&self.config, scopes)?;
+    ) -> Option<Result<Redirect, A::Error>> {
+        let config = self.configs.get(idp)?;
+
+        let pkce_verifier = if config.pkce() {
+            Some(generate_pkce_verifier())
+        } else {
+            None
+        };
+        let pkce_challenge = pkce_verifier.as_deref().map(pkce_code_challenge);
+
+        let oidc_nonce = if config.oidc() {
+            Some(generate_oidc_nonce())
+        } else {
+            None
+        };
+
+        let (uri, state) = match self.adapter.authorization_uri(
+            config,
+            scopes,
+            pkce_challenge.as_deref(),
+            oidc_nonce.as_deref(),
+        ) {
+            Ok(v) => v,
+            Err(e) => return Some(Err(e)),
+        };
+
         cookies.add_private(
             Cookie::build(STATE_COOKIE_NAME, state.clone())
                 .same_site(SameSite::Lax)
                 .finish(),
         );
-        Ok(Redirect::to(uri))
+        cookies.add_private(
+            Cookie::build(IDP_COOKIE_NAME, idp.to_string())
+                .same_site(SameSite::Lax)
+                .finish(),
+        );
+        if let Some(verifier) = pkce_verifier {
+            cookies.add_private(
+                Cookie::build(PKCE_COOKIE_NAME, verifier)
+                    .same_site(SameSite::Lax)
+                    .finish(),
+            );
+        }
+        if let Some(nonce) = oidc_nonce {
+            cookies.add_private(
+                Cookie::build(NONCE_COOKIE_NAME, nonce)
+                    .same_site(SameSite::Lax)
+                    .finish(),
+            );
+        }
+        Some(Ok(Redirect::to(uri)))
+    }
+
+    /// Redeem a previously obtained refresh token for a new access token, as
+    /// described in RFC 6749 §6. If the authorization server's response
+    /// omits `refresh_token`, the given `refresh_token` is preserved on the
+    /// returned `TokenResponse`, since the server is indicating that it is
+    /// still valid. Returns `None` if `idp` does not match any configured
+    /// provider.
+    pub fn refresh(&self, idp: &str, refresh_token: &str) -> Option<Result<TokenResponse, A::Error>> {
+        let config = self.configs.get(idp)?;
+        let mut token = match self.adapter.refresh(config, refresh_token) {
+            Ok(token) => token,
+            Err(e) => return Some(Err(e)),
+        };
+        token.stamp_expiry();
+        if token.refresh_token.is_none() {
+            token.refresh_token = Some(refresh_token.to_string());
+        }
+        Some(Ok(token))
+    }
+
+    /// Fetch an app-level access token via the Client Credentials grant
+    /// (RFC 6749 §4.4). This bypasses the redirect/state/cookie machinery
+    /// entirely, since there is no browser round-trip or resource owner
+    /// involved. Returns `None` if `idp` does not match any configured
+    /// provider.
+    pub fn client_credentials(
+        &self,
+        idp: &str,
+        scopes: &[&str],
+    ) -> Option<Result<TokenResponse, A::Error>> {
+        let config = self.configs.get(idp)?;
+        let mut token = match self.adapter.client_credentials(config, scopes) {
+            Ok(token) => token,
+            Err(e) => return Some(Err(e)),
+        };
+        token.stamp_expiry();
+        Some(Ok(token))
     }
 
     // TODO: Decide if BadRequest is the appropriate error code.
-    // TODO: What do providers do if they *reject* the authorization?
     /// Handle the redirect callback, delegating to the adapter and callback to
     /// perform the token exchange and application-specific actions.
     fn handle<'r>(&self, request: &'r Request<'_>, _data: Data) -> handler::Outcome<'r> {
         // Parse the query data.
         let query = request.uri().query().into_outcome(Status::BadRequest)?;
 
+        // Recover which IdP this flow was started with, so we know which
+        // config to use for the token exchange.
+        let idp = {
+            let mut cookies = request.guard::<Cookies<'_>>().expect("request cookies");
+            match cookies.get_private(IDP_COOKIE_NAME) {
+                Some(cookie) => {
+                    cookies.remove(cookie.clone());
+                    cookie.value().to_string()
+                }
+                None => return handler::Outcome::failure(Status::BadRequest),
+            }
+        };
+
+        let config = match self.configs.get(&idp) {
+            Some(config) => config,
+            None => return handler::Outcome::failure(Status::BadRequest),
+        };
+
         #[derive(FromForm)]
         struct CallbackQuery {
             code: String,
             state: String,
         }
 
+        // The provider redirects back with `error` instead of `code` when it
+        // rejects the authorization request, e.g. the resource owner denied
+        // access (RFC 6749 §4.1.2.1).
+        #[derive(FromForm)]
+        struct CallbackErrorQuery {
+            error: String,
+            error_description: Option<String>,
+            error_uri: Option<String>,
+            state: String,
+        }
+
+        if let Ok(err_params) = CallbackErrorQuery::from_form(&mut FormItems::from(query), false)
+        {
+            {
+                let mut cookies = request.guard::<Cookies<'_>>().expect("request cookies");
+                match cookies.get_private(STATE_COOKIE_NAME) {
+                    Some(ref cookie) if cookie.value() == err_params.state => {
+                        cookies.remove(cookie.clone());
+                    }
+                    _ => {
+                        clear_pkce_and_nonce_cookies(&mut cookies);
+                        return handler::Outcome::failure(Status::BadRequest);
+                    }
+                }
+                clear_pkce_and_nonce_cookies(&mut cookies);
+            }
+
+            let oauth_error = OAuthError {
+                error: OAuthErrorCode::from(err_params.error),
+                error_description: err_params.error_description,
+                error_uri: err_params.error_uri,
+            };
+            log::error!("Authorization failed: {:?}", oauth_error);
+
+            return match self.callback.error(request, &idp, oauth_error) {
+                Ok(responder) => handler::Outcome::from(request, responder),
+                Err(status) => handler::Outcome::failure(status),
+            };
+        }
+
         let params = match CallbackQuery::from_form(&mut FormItems::from(query), false) {
             Ok(p) => p,
             Err(_) => return handler::Outcome::failure(Status::BadRequest),
@@ -325,21 +891,89 @@ impl<A: Adapter, C: Callback> OAuth2<A, C> {
                 Some(ref cookie) if cookie.value() == params.state => {
                     cookies.remove(cookie.clone());
                 }
-                _ => return handler::Outcome::failure(Status::BadRequest),
+                _ => {
+                    clear_pkce_and_nonce_cookies(&mut cookies);
+                    return handler::Outcome::failure(Status::BadRequest);
+                }
             }
         }
 
+        // Retrieve the PKCE code verifier, if one was stashed for this flow.
+        let pkce_verifier = {
+            let mut cookies = request.guard::<Cookies<'_>>().expect("request cookies");
+            cookies
+                .get_private(PKCE_COOKIE_NAME)
+                .map(|cookie| {
+                    cookies.remove(cookie.clone());
+                    cookie.value().to_string()
+                })
+        };
+
         // Have the adapter perform the token exchange.
-        let token = match self.adapter.exchange_code(&self.config, &params.code) {
+        let mut token = match self
+            .adapter
+            .exchange_code(config, &params.code, pkce_verifier.as_deref())
+        {
             Ok(token) => token,
             Err(e) => {
                 log::error!("Token exchange failed: {:?}", e);
-                return handler::Outcome::failure(Status::BadRequest);
+                let mut cookies = request.guard::<Cookies<'_>>().expect("request cookies");
+                clear_pkce_and_nonce_cookies(&mut cookies);
+
+                return match self.adapter.token_error(&e) {
+                    Some(oauth_error) => match self.callback.error(request, &idp, oauth_error) {
+                        Ok(responder) => handler::Outcome::from(request, responder),
+                        Err(status) => handler::Outcome::failure(status),
+                    },
+                    None => handler::Outcome::failure(Status::BadRequest),
+                };
             }
         };
+        token.stamp_expiry();
+
+        // If OIDC is enabled, the provider is required to have returned an
+        // id_token; validate it against the planted nonce and the provider's
+        // JWKS.
+        if config.oidc() {
+            let nonce = {
+                let mut cookies = request.guard::<Cookies<'_>>().expect("request cookies");
+                cookies.get_private(NONCE_COOKIE_NAME).map(|cookie| {
+                    cookies.remove(cookie.clone());
+                    cookie.value().to_string()
+                })
+            };
+
+            match token.extras.get("id_token").and_then(JsonValue::as_str) {
+                Some(id_token) => {
+                    let id_token = id_token.to_string();
+                    let nonce = match nonce {
+                        Some(nonce) => nonce,
+                        None => return handler::Outcome::failure(Status::BadRequest),
+                    };
+                    match validate_id_token(&id_token, config, &nonce) {
+                        Ok(claims) => token.id_claims = Some(claims),
+                        Err(e) => {
+                            log::error!("id_token validation failed: {:?}", e);
+                            return handler::Outcome::failure(Status::BadRequest);
+                        }
+                    }
+                }
+                None => {
+                    // A provider explicitly configured for OIDC that omits
+                    // the id_token defeats the nonce/signature checks
+                    // entirely, so this can't be allowed to silently degrade
+                    // into an un-asserted login.
+                    log::error!(
+                        "IdP \"{}\" is configured for OpenID Connect but the token response had no id_token",
+                        idp
+                    );
+                    return handler::Outcome::failure(Status::BadRequest);
+                }
+            }
+        }
 
         // Run the callback.
-        let responder = self.callback.callback(request, token);
+        let responder = self.callback.callback(request, &idp, token);
         handler::Outcome::from(request, responder)
     }
 }
@@ -360,6 +994,16 @@ fn redirect_handler<'r, A: Adapter, C: Callback>(
     oauth.handle(request, data)
 }
 
+/// Determine which configured IdP a login request is for: read a `<idp>`
+/// dynamic path segment if the route declares one, falling back to an `idp`
+/// query parameter otherwise.
+fn resolve_idp(request: &Request<'_>) -> Option<String> {
+    if let Some(Ok(idp)) = request.get_param::<String>(0) {
+        return Some(idp);
+    }
+    request.get_query_value::<String>("idp").and_then(Result::ok)
+}
+
 /// Handles a login route, performing a redirect
 fn login_handler<'r, A: Adapter, C: Callback>(
     request: &'r Request<'_>,
@@ -370,9 +1014,22 @@ fn login_handler<'r, A: Adapter, C: Callback>(
         Outcome::Failure(_) => return handler::Outcome::failure(Status::InternalServerError),
         Outcome::Forward(()) => unreachable!(),
     };
+
+    let idp = match resolve_idp(request) {
+        Some(idp) => idp,
+        None => return handler::Outcome::failure(Status::NotFound),
+    };
+
+    let scopes: Vec<_> = match oauth.default_scopes.get(&idp) {
+        Some(scopes) => scopes.iter().map(String::as_str).collect(),
+        None => return handler::Outcome::failure(Status::NotFound),
+    };
+
     let mut cookies = request.guard::<Cookies<'_>>().expect("request cookies");
-    let scopes: Vec<_> = oauth.default_scopes.iter().map(String::as_str).collect();
-    handler::Outcome::from(request, oauth.get_redirect(&mut cookies, &scopes))
+    match oauth.get_redirect(&mut cookies, &idp, &scopes) {
+        Some(outcome) => handler::Outcome::from(request, outcome),
+        None => handler::Outcome::failure(Status::NotFound),
+    }
 }
 
 /// A `Provider` contains the authorization and token exchange URIs specific to
@@ -382,6 +1039,12 @@ pub struct Provider {
     pub auth_uri: Cow<'static, str>,
     /// The token exchange URI associated with the service provider.
     pub token_uri: Cow<'static, str>,
+    /// The OpenID Connect issuer identifier for this provider, if it supports
+    /// OIDC. Required (along with `jwks_uri`) to validate an `id_token`.
+    pub issuer: Option<Cow<'static, str>>,
+    /// The URI of this provider's JSON Web Key Set, if it supports OpenID
+    /// Connect. Required (along with `issuer`) to validate an `id_token`.
+    pub jwks_uri: Option<Cow<'static, str>>,
 }
 
 impl Provider {
@@ -400,10 +1063,14 @@ impl Provider {
             Value::Table(t) => {
                 let auth_uri = get_config_string(t, "auth_uri")?.into();
                 let token_uri = get_config_string(t, "token_uri")?.into();
+                let issuer = get_config_string_opt(t, "issuer")?.map(Cow::Owned);
+                let jwks_uri = get_config_string_opt(t, "jwks_uri")?.map(Cow::Owned);
 
                 Ok(Provider {
                     auth_uri,
                     token_uri,
+                    issuer,
+                    jwks_uri,
                 })
             }
             _ => Err(type_error()),
@@ -412,13 +1079,15 @@ impl Provider {
 }
 
 macro_rules! providers {
-    (@ $(($name:ident $docstr:expr) : $auth:expr, $token:expr),*) => {
+    (@ $(($name:ident $docstr:expr) : $auth:expr, $token:expr, $issuer:expr, $jwks:expr),*) => {
         $(
             #[doc = $docstr]
             #[allow(non_upper_case_globals)]
             pub const $name: Provider = Provider {
                 auth_uri: Cow::Borrowed($auth),
                 token_uri: Cow::Borrowed($token),
+                issuer: $issuer,
+                jwks_uri: $jwks,
             };
         )*
 
@@ -433,16 +1102,101 @@ macro_rules! providers {
             }
         }
     };
-    ($($name:ident : $auth:expr, $token:expr),* $(,)*) => {
-        providers!(@ $(($name concat!("A `Provider` suitable for authorizing users with ", stringify!($name), ".")) : $auth, $token),*);
+    ($($name:ident : $auth:expr, $token:expr, $issuer:expr, $jwks:expr),* $(,)*) => {
+        providers!(@ $(($name concat!("A `Provider` suitable for authorizing users with ", stringify!($name), ".")) : $auth, $token, $issuer, $jwks),*);
     };
 }
 
 providers! {
-    Discord: "https://discordapp.com/api/oauth2/authorize", "https://discordapp.com/api/oauth2/token",
-    Facebook: "https://www.facebook.com/v3.1/dialog/oauth", "https://graph.facebook.com/v3.1/oauth/access_token",
-    GitHub: "https://github.com/login/oauth/authorize", "https://github.com/login/oauth/access_token",
-    Google: "https://accounts.google.com/o/oauth2/v2/auth", "https://www.googleapis.com/oauth2/v4/token",
-    Reddit: "https://www.reddit.com/api/v1/authorize", "https://www.reddit.com/api/v1/access_token",
-    Yahoo: "https://api.login.yahoo.com/oauth2/request_auth", "https://api.login.yahoo.com/oauth2/get_token",
+    Discord: "https://discordapp.com/api/oauth2/authorize", "https://discordapp.com/api/oauth2/token", None, None,
+    Facebook: "https://www.facebook.com/v3.1/dialog/oauth", "https://graph.facebook.com/v3.1/oauth/access_token", None, None,
+    GitHub: "https://github.com/login/oauth/authorize", "https://github.com/login/oauth/access_token", None, None,
+    Google: "https://accounts.google.com/o/oauth2/v2/auth", "https://www.googleapis.com/oauth2/v4/token", Some(Cow::Borrowed("https://accounts.google.com")), Some(Cow::Borrowed("https://www.googleapis.com/oauth2/v3/certs")),
+    Reddit: "https://www.reddit.com/api/v1/authorize", "https://www.reddit.com/api/v1/access_token", None, None,
+    Yahoo: "https://api.login.yahoo.com/oauth2/request_auth", "https://api.login.yahoo.com/oauth2/get_token", None, None,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn oauth_error_code_maps_known_codes() {
+        assert_eq!(
+            OAuthErrorCode::from("invalid_request".to_string()),
+            OAuthErrorCode::InvalidRequest
+        );
+        assert_eq!(
+            OAuthErrorCode::from("invalid_client".to_string()),
+            OAuthErrorCode::InvalidClient
+        );
+        assert_eq!(
+            OAuthErrorCode::from("invalid_grant".to_string()),
+            OAuthErrorCode::InvalidGrant
+        );
+        assert_eq!(
+            OAuthErrorCode::from("unauthorized_client".to_string()),
+            OAuthErrorCode::UnauthorizedClient
+        );
+        assert_eq!(
+            OAuthErrorCode::from("unsupported_grant_type".to_string()),
+            OAuthErrorCode::UnsupportedGrantType
+        );
+        assert_eq!(
+            OAuthErrorCode::from("invalid_scope".to_string()),
+            OAuthErrorCode::InvalidScope
+        );
+    }
+
+    #[test]
+    fn oauth_error_code_preserves_unknown_codes() {
+        assert_eq!(
+            OAuthErrorCode::from("consent_required".to_string()),
+            OAuthErrorCode::Other("consent_required".to_string())
+        );
+    }
+
+    #[test]
+    fn pkce_code_challenge_matches_rfc_7636_appendix_b() {
+        // https://www.rfc-editor.org/rfc/rfc7636#appendix-B
+        let verifier = "dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk";
+        let challenge = pkce_code_challenge(verifier);
+        assert_eq!(challenge, "E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cM");
+    }
+
+    fn token_with_expires_in(expires_in: Option<i32>) -> TokenResponse {
+        TokenResponse {
+            access_token: "access".to_string(),
+            token_type: "Bearer".to_string(),
+            expires_in,
+            refresh_token: None,
+            scope: None,
+            extras: HashMap::new(),
+            expires_at: None,
+            id_claims: None,
+        }
+    }
+
+    #[test]
+    fn stamp_expiry_without_expires_in_never_expires() {
+        let mut token = token_with_expires_in(None);
+        token.stamp_expiry();
+        assert_eq!(token.expires_at(), None);
+        assert!(!token.is_expired());
+    }
+
+    #[test]
+    fn stamp_expiry_with_expires_in_is_not_yet_expired() {
+        let mut token = token_with_expires_in(Some(3600));
+        token.stamp_expiry();
+        assert!(token.expires_at().is_some());
+        assert!(!token.is_expired());
+    }
+
+    #[test]
+    fn stamp_expiry_with_zero_expires_in_is_immediately_expired() {
+        let mut token = token_with_expires_in(Some(0));
+        token.stamp_expiry();
+        assert!(token.is_expired());
+    }
 }